@@ -0,0 +1,148 @@
+use anyhow::{anyhow, Result};
+
+use crate::protocol::{self, Command, Frame};
+
+const FRAME_HEAD: u8 = 0xBB;
+const FRAME_TAIL: u8 = 0x7E;
+const HEADER_LEN: usize = 5;
+
+/// Tries to pull one complete [`Frame`] out of `src`, returning the number of bytes it
+/// consumed alongside it. Returns `Ok(None)` when `src` doesn't yet hold a full frame;
+/// partial data is left untouched so more can be appended by the caller. Returns `Err` if
+/// `src` holds a full header that doesn't start with `0xBB`, or a full frame's worth of
+/// bytes that don't parse as one (bad tail, bad checksum, or an unrecognized command byte)
+/// -- see [`SyncM100Codec::decode`] for how callers recover from that.
+fn decode_frame(src: &[u8]) -> Result<Option<(usize, Frame)>> {
+    if src.len() < HEADER_LEN {
+        return Ok(None);
+    }
+    if src[0] != FRAME_HEAD {
+        return Err(anyhow!("invalid frame head: {:#04x}", src[0]));
+    }
+
+    let length = u16::from_be_bytes([src[3], src[4]]) as usize;
+    let total_len = HEADER_LEN + length + 2; // + checksum byte + tail
+    if src.len() < total_len {
+        return Ok(None);
+    }
+
+    let tail = src[total_len - 1];
+    if tail != FRAME_TAIL {
+        return Err(anyhow!("invalid frame tail: {:#04x}", tail));
+    }
+
+    let checksum: u32 = src[1..total_len - 2].iter().map(|b| *b as u32).sum();
+    let checksum = (checksum & 0xFF) as u8;
+    let received_checksum = src[total_len - 2];
+    if received_checksum != checksum {
+        return Err(anyhow!(
+            "invalid frame checksum: expected {:#04x}, got {:#04x}",
+            checksum,
+            received_checksum
+        ));
+    }
+
+    let frame = Frame {
+        frame_type: src[1],
+        command: Command::try_from(src[2])?,
+        payload: src[HEADER_LEN..HEADER_LEN + length].to_vec(),
+    };
+
+    Ok(Some((total_len, frame)))
+}
+
+/// Pulls complete [`Frame`]s out of a byte buffer for callers that drive the serial port
+/// with blocking reads, used by [`M100Device::receive_frame`](crate::m100::M100Device).
+#[derive(Debug, Default)]
+pub struct SyncM100Codec;
+
+impl SyncM100Codec {
+    /// Tries to pull one complete [`Frame`] out of the front of `buf`, draining the bytes
+    /// it consumed. Returns `Ok(None)` if `buf` doesn't yet hold a full frame.
+    ///
+    /// A leading byte that can't start a valid frame (line noise, or the tail end of a
+    /// frame cut short by a read timeout) is dropped and parsing retried from the next byte,
+    /// so a single corrupted byte resyncs on the next `0xBB` instead of wedging every future
+    /// call on the same error.
+    pub fn decode(&mut self, buf: &mut Vec<u8>) -> Result<Option<Frame>> {
+        loop {
+            match decode_frame(buf) {
+                Ok(Some((consumed, frame))) => {
+                    buf.drain(0..consumed);
+                    return Ok(Some(frame));
+                }
+                Ok(None) => return Ok(None),
+                Err(e) => {
+                    if buf.is_empty() {
+                        return Err(e);
+                    }
+                    buf.remove(0);
+                }
+            }
+        }
+    }
+
+    pub fn encode(&mut self, frame: Frame, dst: &mut Vec<u8>) -> Result<()> {
+        dst.extend_from_slice(&protocol::make_frame(frame.command, &frame.payload)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_then_encode_round_trips_a_frame() {
+        let packet = protocol::make_frame(Command::GetVersion, b"v1.0").unwrap();
+        let mut buf = packet.clone();
+
+        let mut codec = SyncM100Codec;
+        let frame = codec.decode(&mut buf).unwrap().unwrap();
+        assert!(buf.is_empty());
+        assert_eq!(frame.payload, b"v1.0");
+
+        let mut encoded = Vec::new();
+        codec.encode(frame, &mut encoded).unwrap();
+        assert_eq!(encoded, packet);
+    }
+
+    #[test]
+    fn decode_returns_none_and_leaves_a_partial_frame_untouched() {
+        let packet = protocol::make_frame(Command::GetVersion, b"v1.0").unwrap();
+        let mut buf = packet[..packet.len() - 1].to_vec();
+        let partial = buf.clone();
+
+        let mut codec = SyncM100Codec;
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert_eq!(buf, partial);
+    }
+
+    #[test]
+    fn decode_drops_a_leading_stray_byte_and_recovers() {
+        let packet = protocol::make_frame(Command::GetVersion, b"v1.0").unwrap();
+        let mut buf = vec![0x00]; // line noise that can't start a frame
+        buf.extend_from_slice(&packet);
+
+        let mut codec = SyncM100Codec;
+        let frame = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(frame.payload, b"v1.0");
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn decode_resyncs_past_a_corrupted_frame_instead_of_erroring_forever() {
+        let mut corrupt = protocol::make_frame(Command::GetVersion, b"v1.0").unwrap();
+        let checksum_index = corrupt.len() - 2;
+        corrupt[checksum_index] ^= 0xFF; // still a full frame's worth of bytes, bad checksum
+        let good = protocol::make_frame(Command::GetVersion, b"v2.0").unwrap();
+
+        let mut buf = corrupt;
+        buf.extend_from_slice(&good);
+
+        let mut codec = SyncM100Codec;
+        let frame = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(frame.payload, b"v2.0");
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::error::M100Error;
+use crate::m100::TagInfo;
+use crate::protocol::{Command, Frame};
+
+/// A typed, successfully-decoded response to a [`Command`], produced by [`dispatch`].
+#[derive(Debug)]
+pub enum Response {
+    Version(String),
+    Tag(Option<TagInfo>),
+    MemoryData(Vec<u8>),
+    Ack,
+}
+
+/// Declares, for each [`Command`], the status bytes the firmware can reply with (each mapped
+/// to an [`M100Error`] variant) and the parser to run when the payload isn't one of those
+/// status-only error replies. Generates [`dispatch`], which maps a raw [`Frame`] to a typed
+/// [`Response`] or a matchable [`M100Error`] instead of callers re-checking magic bytes.
+macro_rules! command_table {
+    ($($variant:ident {
+        errors: { $($status:literal => $err:ident),* $(,)? },
+        parse: $parse:expr $(,)?
+    }),* $(,)?) => {
+        pub fn dispatch(frame: &Frame) -> Result<Response, M100Error> {
+            match frame.command {
+                $(
+                    Command::$variant => {
+                        let payload = &frame.payload;
+                        if payload.len() == 1 {
+                            match payload[0] {
+                                $($status => return Err(M100Error::$err),)*
+                                _ => {}
+                            }
+                        }
+                        let parse: fn(&[u8]) -> Result<Response, M100Error> = $parse;
+                        parse(payload)
+                    }
+                )*
+            }
+        }
+    };
+}
+
+command_table! {
+    GetVersion {
+        errors: {},
+        parse: |payload| {
+            std::str::from_utf8(payload)
+                .map(|version| Response::Version(version.to_string()))
+                .map_err(|_| M100Error::InvalidResponse)
+        },
+    },
+    Idle {
+        errors: {},
+        parse: |_payload| Ok(Response::Ack),
+    },
+    Query {
+        errors: {},
+        parse: |payload| {
+            if payload.len() <= 1 {
+                return Ok(Response::Tag(None));
+            }
+            if payload.len() < 5 {
+                return Err(M100Error::InvalidResponse);
+            }
+            let rssi = payload[0];
+            let epc = hex::encode(&payload[3..payload.len() - 2]).to_uppercase();
+            Ok(Response::Tag(Some(TagInfo { epc, rssi })))
+        },
+    },
+    MultiplePolling {
+        errors: {},
+        parse: |_payload| Ok(Response::Ack),
+    },
+    ReadData {
+        errors: {
+            0x09 => ReadFailed,
+            0xA3 => MemoryOverrun,
+        },
+        parse: |payload| Ok(Response::MemoryData(payload.to_vec())),
+    },
+    SetHfss {
+        errors: {},
+        parse: |_payload| Ok(Response::Ack),
+    },
+    WriteData {
+        errors: {
+            0xB0 => WriteFailed,
+            0x10 => WriteFailed,
+        },
+        parse: |_payload| Ok(Response::Ack),
+    },
+}
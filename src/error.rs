@@ -0,0 +1,33 @@
+use std::fmt;
+
+/// A decoded, matchable error reported by the M100 firmware itself, as opposed to a
+/// transport- or framing-level failure (those still surface as `anyhow::Error` from
+/// `protocol`/`codec`). Produced by [`crate::dispatch::dispatch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum M100Error {
+    /// `HEXIN_ERROR_WRITE` / `HEXIN_FAIL_WRITE`: the firmware could not write the requested bank.
+    WriteFailed,
+    /// `HEXIN_FAIL_READ`: the firmware could not read the requested bank.
+    ReadFailed,
+    /// `HEXIN_ERROR_READ_MEMORY_OVERRUN`: the requested address/length ran past the bank's end.
+    MemoryOverrun,
+    /// The firmware refused the request because the access password didn't match.
+    AccessDenied,
+    /// The response payload didn't have the shape the command's parser expected.
+    InvalidResponse,
+}
+
+impl fmt::Display for M100Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            M100Error::WriteFailed => "firmware reported a write failure",
+            M100Error::ReadFailed => "firmware reported a read failure",
+            M100Error::MemoryOverrun => "firmware reported a memory overrun",
+            M100Error::AccessDenied => "firmware denied access to the requested memory bank",
+            M100Error::InvalidResponse => "response payload did not match the expected shape",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl std::error::Error for M100Error {}
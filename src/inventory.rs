@@ -0,0 +1,37 @@
+use std::time::Instant;
+
+/// The de-duplicated record of one EPC seen during an [`M100Device::inventory`](crate::m100::M100Device::inventory)
+/// scan: when it first/last showed up and how its RSSI moved across reads.
+#[derive(Debug, Clone)]
+pub struct InventoryEntry {
+    pub epc: String,
+    pub first_seen: Instant,
+    pub last_seen: Instant,
+    pub min_rssi: u8,
+    pub max_rssi: u8,
+    pub last_rssi: u8,
+    pub read_count: u32,
+}
+
+impl InventoryEntry {
+    pub(crate) fn new(epc: String, rssi: u8) -> InventoryEntry {
+        let now = Instant::now();
+        InventoryEntry {
+            epc,
+            first_seen: now,
+            last_seen: now,
+            min_rssi: rssi,
+            max_rssi: rssi,
+            last_rssi: rssi,
+            read_count: 1,
+        }
+    }
+
+    pub(crate) fn record(&mut self, rssi: u8) {
+        self.last_seen = Instant::now();
+        self.last_rssi = rssi;
+        self.min_rssi = self.min_rssi.min(rssi);
+        self.max_rssi = self.max_rssi.max(rssi);
+        self.read_count += 1;
+    }
+}
@@ -1,42 +1,84 @@
-use std::time::Duration;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
 use serialport::{DataBits, SerialPort, StopBits};
 
-use crate::protocol;
+use crate::codec::SyncM100Codec;
+use crate::dispatch::{dispatch, Response};
+use crate::inventory::InventoryEntry;
+use crate::protocol::{self, Frame};
+use crate::transport::M100Transport;
 
 pub static DEFAULT_PASSWORD: [u8; 8] = [0x30; 8];
 
-pub struct M100Device {
-    port: Box<dyn SerialPort>,
+/// Size of each block written during [`M100Device::upload_firmware`].
+const FIRMWARE_CHUNK_SIZE: usize = 256;
+/// Byte the bootloader sends back after a chunk or the final checksum was accepted.
+const FIRMWARE_CHUNK_ACK: u8 = 0x00; // observed ACK byte; anything else is treated as a NAK
+
+/// Number of polling rounds requested per [`M100Device::inventory`] call; large enough that
+/// the reader keeps reporting tags for the whole scan window rather than stopping early.
+const INVENTORY_POLLING_CYCLES: u16 = 0xFFFF;
+
+pub struct M100Device<T: M100Transport = Box<dyn SerialPort>> {
+    transport: T,
     read_buf: [u8; 1024],
+    /// Bytes read off the wire but not yet consumed into a full frame, so a read timeout
+    /// mid-frame loses nothing -- the next call to [`M100Device::receive_frame`] picks up
+    /// right where this one left off instead of re-reading from a fixed offset.
+    recv_buf: Vec<u8>,
+    codec: SyncM100Codec,
 }
 
-impl M100Device {
+impl M100Device<Box<dyn SerialPort>> {
     pub fn new(mut port: Box<dyn SerialPort>) -> Result<M100Device> {
         port.set_stop_bits(StopBits::One)?;
         port.set_data_bits(DataBits::Eight)?;
         port.set_timeout(Duration::from_secs(1))?;
 
-        Ok(M100Device {
-            port,
+        Ok(M100Device::with_transport(port))
+    }
+}
+
+impl<T: M100Transport> M100Device<T> {
+    /// Builds a device directly from an [`M100Transport`], skipping the serial-port-specific
+    /// setup in [`M100Device::new`]. Used to drive the device logic against a `MockTransport`.
+    pub fn with_transport(transport: T) -> M100Device<T> {
+        M100Device {
+            transport,
             read_buf: [0; 1024],
-        })
+            recv_buf: Vec::new(),
+            codec: SyncM100Codec,
+        }
     }
 
     pub fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
-        self.port.set_baud_rate(baud_rate)?;
+        self.transport.set_baud_rate(baud_rate)?;
         Ok(())
     }
 
-    pub fn upload_firmware(&mut self, firmware: &[u8]) -> Result<()> {
-        self.port.set_baud_rate(9600)?;
+    /// Flashes `firmware` block-by-block, ACKing and retrying each chunk up to
+    /// `max_retries` times before giving up, then hands the bootloader a running checksum
+    /// of the whole image so it can reject a corrupt upload. `on_progress` is called after
+    /// every successfully ACK'd chunk with `(bytes_done, total)` so callers can drive a
+    /// progress bar.
+    pub fn upload_firmware<F>(
+        &mut self,
+        firmware: &[u8],
+        max_retries: u32,
+        mut on_progress: F,
+    ) -> Result<()>
+    where
+        F: FnMut(usize, usize),
+    {
+        self.transport.set_baud_rate(9600)?;
 
         // stage 1 -- confirm the m100 is alive
-        self.port.write(&[0xFE])?;
-        self.port.flush()?;
+        self.transport.write_all(&[0xFE])?;
+        self.transport.flush()?;
 
-        self.port.read_exact(&mut self.read_buf[0..1])?;
+        self.transport.read_exact(&mut self.read_buf[0..1])?;
         if self.read_buf[0] != 0xFF {
             return Err(anyhow!(
                 "Could not establish connection to the device: {:#04x}",
@@ -45,78 +87,173 @@ impl M100Device {
         }
 
         // stage 2 -- set baud rate to 115200
-        self.port.write(&[0xB5])?;
-        self.port.flush()?;
+        self.transport.write_all(&[0xB5])?;
+        self.transport.flush()?;
         std::thread::sleep(Duration::from_millis(50));
-        self.port.set_baud_rate(115200)?;
+        self.transport.set_baud_rate(115200)?;
 
         // stage 3 -- prepare to upload firmware
-        self.port.write(&[0xFF, 0xDB])?;
-        self.port.flush()?;
+        self.transport.write_all(&[0xFF, 0xDB])?;
+        self.transport.flush()?;
 
-        self.port.read_exact(&mut self.read_buf[0..1])?;
+        self.transport.read_exact(&mut self.read_buf[0..1])?;
         if self.read_buf[0] != 0xBF {
             return Err(anyhow!(
                 "Could not prepare firmware upload to the device: {:#04x}",
                 self.read_buf[0]
             ));
         }
-        self.port.write(&[0xFD])?;
-        self.port.flush()?;
+        self.transport.write_all(&[0xFD])?;
+        self.transport.flush()?;
+
+        // stage 4 -- upload the firmware block by block, retrying a chunk on NAK or timeout
+        let total = firmware.len();
+        let mut bytes_done = 0;
+        let mut checksum: u32 = 0;
+        for chunk in firmware.chunks(FIRMWARE_CHUNK_SIZE) {
+            let mut attempt = 0;
+            loop {
+                self.transport.write_all(chunk)?;
+                self.transport.flush()?;
+
+                match self.transport.read_exact(&mut self.read_buf[0..1]) {
+                    Ok(()) if self.read_buf[0] == FIRMWARE_CHUNK_ACK => break,
+                    Ok(()) if attempt < max_retries => attempt += 1,
+                    Ok(()) => {
+                        return Err(anyhow!(
+                            "Chunk at offset {} was NAK'd ({:#04x}) after {} retries",
+                            bytes_done,
+                            self.read_buf[0],
+                            max_retries
+                        ))
+                    }
+                    Err(_) if attempt < max_retries => attempt += 1,
+                    Err(e) => {
+                        return Err(anyhow!(
+                            "Chunk at offset {} timed out after {} retries: {}",
+                            bytes_done,
+                            max_retries,
+                            e
+                        ))
+                    }
+                }
+            }
+
+            checksum = checksum.wrapping_add(chunk.iter().map(|b| *b as u32).sum());
+            bytes_done += chunk.len();
+            on_progress(bytes_done, total);
+        }
 
-        // stage 4 -- upload the firmware
-        self.port.write(firmware)?;
-        self.port.flush()?;
+        // stage 5 -- send the whole-image checksum so the bootloader can reject a corrupt upload
+        self.transport.write_all(&(checksum as u16).to_be_bytes())?;
+        self.transport.flush()?;
 
-        // stage 5 -- disable sleep mode
+        self.transport.read_exact(&mut self.read_buf[0..1])?;
+        if self.read_buf[0] != FIRMWARE_CHUNK_ACK {
+            return Err(anyhow!(
+                "Device rejected the firmware checksum: {:#04x}",
+                self.read_buf[0]
+            ));
+        }
+
+        // stage 6 -- re-establish the link and confirm the new firmware is running
+        self.transport.set_baud_rate(115200)?;
+        self.get_version()?;
+
+        // stage 7 -- disable sleep mode
         self.disable_sleep()?;
 
         Ok(())
     }
 
-    pub fn get_version(&mut self) -> Result<&str> {
+    pub fn get_version(&mut self) -> Result<String> {
         // mode 0x00 = hardware
         // mode 0x01 = software
         // mode 0x02 = manufacturer
 
         let command = protocol::get_version()?;
-        self.port.write(&command)?;
-        self.port.flush()?;
-
-        let res = self.receive_response()?;
+        self.transport.write_all(&command)?;
+        self.transport.flush()?;
 
-        Ok(std::str::from_utf8(res)?)
+        let frame = self.receive_frame()?;
+        match dispatch(&frame)? {
+            Response::Version(version) => Ok(version),
+            other => Err(anyhow!("unexpected response to GetVersion: {:?}", other)),
+        }
     }
 
     pub fn set_hfss_status(&mut self, status: HfssStatus) -> Result<()> {
         let command = protocol::set_hfss_status(status)?;
-        self.port.write(&command)?;
-        self.port.flush()?;
+        self.transport.write_all(&command)?;
+        self.transport.flush()?;
 
-        self.receive_response()?;
+        let frame = self.receive_frame()?;
+        dispatch(&frame)?;
 
         Ok(())
     }
 
     pub fn query(&mut self) -> Result<Option<TagInfo>> {
         let command = protocol::query()?;
-        self.port.write(&command)?;
-        self.port.flush()?;
+        self.transport.write_all(&command)?;
+        self.transport.flush()?;
 
-        let res = self.receive_response()?;
-        if res.len() <= 1 {
-            return Ok(None);
+        let frame = self.receive_frame()?;
+        match dispatch(&frame)? {
+            Response::Tag(tag) => Ok(tag),
+            other => Err(anyhow!("unexpected response to Query: {:?}", other)),
         }
-        let rssi = res[0];
-        let epc = hex::encode(&res[3..res.len() - 2]).to_uppercase();
-        Ok(Some(TagInfo { epc, rssi }))
+    }
+
+    /// Scans for `duration`, collecting every EPC the reader reports via its multiple-polling
+    /// instruction. Tags are de-duplicated by EPC, tracking first/last-seen timestamps and
+    /// min/max/last RSSI across all of their reads. `on_update` is called with the
+    /// in-progress table every time a read updates it, so callers can drive a live view.
+    ///
+    /// Reports are drained through [`M100Device::receive_frame`] like every other response on
+    /// this (fully synchronous) device -- there's no separate async frame stream to speak of.
+    pub fn inventory<F>(
+        &mut self,
+        duration: Duration,
+        mut on_update: F,
+    ) -> Result<Vec<InventoryEntry>>
+    where
+        F: FnMut(&HashMap<String, InventoryEntry>),
+    {
+        let command = protocol::multiple_polling(INVENTORY_POLLING_CYCLES)?;
+        self.transport.write_all(&command)?;
+        self.transport.flush()?;
+
+        let mut tags: HashMap<String, InventoryEntry> = HashMap::new();
+        let deadline = Instant::now() + duration;
+
+        while Instant::now() < deadline {
+            let frame = match self.receive_frame() {
+                Ok(frame) => frame,
+                Err(_) => continue, // a read timeout just means no tag was seen this tick
+            };
+
+            let tag = match dispatch(&frame) {
+                Ok(Response::Tag(Some(tag))) => tag,
+                _ => continue, // no tag in this frame, or it's the polling-complete notification
+            };
+
+            tags.entry(tag.epc.clone())
+                .and_modify(|entry| entry.record(tag.rssi))
+                .or_insert_with(|| InventoryEntry::new(tag.epc, tag.rssi));
+            on_update(&tags);
+        }
+
+        self.disable_sleep()?;
+
+        Ok(tags.into_values().collect())
     }
 
     pub fn read_all_data(&mut self, password: &[u8; 8], bank: MemoryBank) -> Result<Vec<u8>> {
         match bank {
             MemoryBank::Reserved => Err(anyhow!("cannot read_all_data the Reserved memory bank")),
             MemoryBank::Epc => Ok(self.read_chunked_data(password, bank, 12, 2)?),
-            MemoryBank::Tid => Ok(self.read_data(password, bank, 0, 32)?.to_vec()),
+            MemoryBank::Tid => self.read_data(password, bank, 0, 32),
             MemoryBank::User => Ok(self.read_chunked_data(password, bank, 0, 512)?),
         }
     }
@@ -133,20 +270,17 @@ impl M100Device {
         // read all the data up to the start address
         if start_address != 0 {
             let start_data = self.read_data(password, bank, 0, start_address)?;
-            data.extend_from_slice(start_data);
+            data.extend_from_slice(&start_data);
         }
 
         let mut address = start_address;
         loop {
             match self.read_data(password, bank, address, chunk_size) {
                 Ok(chunk) => {
-                    data.extend_from_slice(chunk);
+                    data.extend_from_slice(&chunk);
                     address += chunk_size;
                 }
-                Err(e) => {
-                    println!("Error {} at {}.", e, address);
-                    return Ok(data);
-                }
+                Err(_) => return Ok(data),
             }
         }
     }
@@ -162,17 +296,11 @@ impl M100Device {
             MemoryBank::Epc => protocol::write_epc(password, data),
             other => protocol::write_data(password, other, address, data),
         }?;
-        self.port.write(&command)?;
-        self.port.flush()?;
-
-        let res = self.receive_response()?;
-        if res.len() == 1 {
-            if res[0] == 0xB0 {
-                return Err(anyhow!("Unexpected write response: HEXIN_ERROR_WRITE"));
-            } else if res[0] == 0x10 {
-                return Err(anyhow!("Unexpected write response: HEXIN_FAIL_WRITE"));
-            }
-        }
+        self.transport.write_all(&command)?;
+        self.transport.flush()?;
+
+        let frame = self.receive_frame()?;
+        dispatch(&frame)?;
 
         Ok(())
     }
@@ -183,7 +311,7 @@ impl M100Device {
         bank: MemoryBank,
         address: u16,
         data_length: u16,
-    ) -> Result<&[u8]> {
+    ) -> Result<Vec<u8>> {
         if data_length % 2 != 0 || data_length == 0 {
             return Err(anyhow!(
                 "Data length must be a positive even number: {}",
@@ -191,50 +319,45 @@ impl M100Device {
             ));
         }
         let command = protocol::read_data(password, bank, address, data_length)?;
-        self.port.write(&command)?;
-        self.port.flush()?;
-
-        let res = self.receive_response()?;
-        println!("res: {:2x}", res[0]);
-        if res.len() == 1 {
-            if res[0] == 0x09 {
-                return Err(anyhow!("Read failure HEXIN_FAIL_READ"));
-            } else if res[0] == 0xA3 {
-                return Err(anyhow!("Read failure HEXIN_ERROR_READ_MEMORY_OVERRUN"));
-            }
-        }
+        self.transport.write_all(&command)?;
+        self.transport.flush()?;
 
-        Ok(res)
+        let frame = self.receive_frame()?;
+        match dispatch(&frame)? {
+            Response::MemoryData(data) => Ok(data),
+            other => Err(anyhow!("unexpected response to ReadData: {:?}", other)),
+        }
     }
 
     fn disable_sleep(&mut self) -> Result<()> {
         let command = protocol::idle()?;
-        self.port.write(&command)?;
-        self.port.flush()?;
+        self.transport.write_all(&command)?;
+        self.transport.flush()?;
 
-        self.receive_response()?;
+        let frame = self.receive_frame()?;
+        dispatch(&frame)?;
 
         Ok(())
     }
 
-    fn receive_response(&mut self) -> Result<&[u8]> {
-        self.port.read_exact(&mut self.read_buf[0..5])?;
-        let length = i16::from_be_bytes([self.read_buf[3], self.read_buf[4]]); // header
-                                                                               // println!("Incoming data length from response: {}", length);
-        self.port
-            .read_exact(&mut self.read_buf[5..5 + length as usize])?; // body
-        self.port
-            .read_exact(&mut self.read_buf[5 + length as usize..7 + length as usize])?; // end
-
-        let tail = self.read_buf[length as usize + 6];
-        if tail != 0x7E {
-            return Err(anyhow!("Invalid packet (received invalid tail: {})", tail));
-        }
-
-        let unpacked = &self.read_buf[5..length as usize + 5];
-        // println!("{:02X?}", unpacked);
+    /// Reads one full frame off the wire via [`SyncM100Codec`], which verifies the checksum,
+    /// parses the header fields and payload (without yet interpreting what the payload means
+    /// -- see [`dispatch`] for that), and resyncs past any corrupted bytes on its own.
+    ///
+    /// Bytes are pulled in one at a time and appended to `recv_buf` until the codec reports a
+    /// complete frame, so a read timeout (routine during [`M100Device::inventory`]'s scan
+    /// window) never throws away bytes that were already received -- the next call resumes
+    /// from the same buffer instead of re-reading from a fixed offset.
+    fn receive_frame(&mut self) -> Result<Frame> {
+        loop {
+            if let Some(frame) = self.codec.decode(&mut self.recv_buf)? {
+                return Ok(frame);
+            }
 
-        Ok(unpacked)
+            let mut byte = [0u8; 1];
+            self.transport.read_exact(&mut byte)?;
+            self.recv_buf.push(byte[0]);
+        }
     }
 }
 
@@ -259,3 +382,205 @@ pub enum MemoryBank {
     Tid = 0x02,
     User = 0x03,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Command;
+    use crate::transport::MockTransport;
+
+    impl M100Device<MockTransport> {
+        /// Fails the test unless every scripted request/response pair was actually sent and
+        /// consumed, so a device that stops short partway through never passes silently.
+        fn assert_transport_done(&self) {
+            self.transport.assert_done();
+        }
+    }
+
+    // The firmware echoes the command byte of the request it's replying to, which is what
+    // `dispatch` uses to pick a parser -- `cmd` must match the `Command` the test exercises.
+    fn response_frame(cmd: Command, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0xBB, 0x01, cmd as u8];
+        packet.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        packet.extend_from_slice(payload);
+        let checksum: u32 = packet.iter().skip(1).map(|b| *b as u32).sum();
+        packet.push((checksum & 0xFF) as u8);
+        packet.push(0x7E);
+        packet
+    }
+
+    // Steps common to every successful stage 1-3 handshake in upload_firmware, before the
+    // per-test chunk/checksum/post-upload steps.
+    fn handshake_steps() -> Vec<(Vec<u8>, Vec<u8>)> {
+        vec![
+            (vec![0xFE], vec![0xFF]),
+            (vec![0xB5], vec![]),
+            (vec![0xFF, 0xDB], vec![0xBF]),
+            (vec![0xFD], vec![]),
+        ]
+    }
+
+    #[test]
+    fn upload_firmware_retries_a_nak_then_succeeds() {
+        let firmware = vec![0xAA; 10]; // well under FIRMWARE_CHUNK_SIZE -- a single chunk
+        let checksum = firmware.iter().map(|b| *b as u32).sum::<u32>() as u16;
+
+        let get_version_request = protocol::get_version().unwrap();
+        let get_version_response = response_frame(Command::GetVersion, b"v1.0");
+        let idle_request = protocol::idle().unwrap();
+        let idle_response = response_frame(Command::Idle, &[]);
+
+        let mut steps = handshake_steps();
+        steps.push((firmware.clone(), vec![0x01])); // first attempt NAK'd
+        steps.push((firmware.clone(), vec![0x00])); // retry ACK'd
+        steps.push((checksum.to_be_bytes().to_vec(), vec![0x00]));
+        steps.push((get_version_request, get_version_response));
+        steps.push((idle_request, idle_response));
+
+        let mut device = M100Device::with_transport(MockTransport::new(steps));
+
+        let mut progress = Vec::new();
+        device
+            .upload_firmware(&firmware, 1, |done, total| progress.push((done, total)))
+            .unwrap();
+
+        assert_eq!(progress, vec![(firmware.len(), firmware.len())]);
+        device.assert_transport_done();
+    }
+
+    #[test]
+    fn upload_firmware_fails_when_retries_are_exhausted() {
+        let firmware = vec![0x11; 5];
+
+        let mut steps = handshake_steps();
+        steps.push((firmware.clone(), vec![0x01])); // attempt 0, NAK'd, retries remain
+        steps.push((firmware.clone(), vec![0x01])); // attempt 1, NAK'd, out of retries
+
+        let mut device = M100Device::with_transport(MockTransport::new(steps));
+
+        let err = device.upload_firmware(&firmware, 1, |_, _| {}).unwrap_err();
+        assert!(err.to_string().contains("NAK'd"));
+        device.assert_transport_done();
+    }
+
+    #[test]
+    fn upload_firmware_fails_when_the_checksum_is_rejected() {
+        let firmware = vec![0x22; 5];
+        let checksum = firmware.iter().map(|b| *b as u32).sum::<u32>() as u16;
+
+        let mut steps = handshake_steps();
+        steps.push((firmware.clone(), vec![0x00])); // chunk ACK'd on the first attempt
+        steps.push((checksum.to_be_bytes().to_vec(), vec![0x01])); // checksum NAK'd
+
+        let mut device = M100Device::with_transport(MockTransport::new(steps));
+
+        let err = device.upload_firmware(&firmware, 0, |_, _| {}).unwrap_err();
+        assert!(err.to_string().contains("rejected the firmware checksum"));
+        device.assert_transport_done();
+    }
+
+    #[test]
+    fn query_parses_a_tag_report() {
+        let request = protocol::query().unwrap();
+        // payload shape: RSSI(1) + PC word(2) + EPC(N) + tag CRC(2)
+        let response = response_frame(Command::Query, &[0xC0, 0x00, 0x00, 0xAB, 0xCD, 0x12, 0x34]);
+
+        let mut device = M100Device::with_transport(MockTransport::new(vec![(request, response)]));
+        let tag = device.query().unwrap().unwrap();
+
+        assert_eq!(tag.epc, "ABCD");
+        assert_eq!(tag.rssi, 0xC0);
+        device.assert_transport_done();
+    }
+
+    #[test]
+    fn query_with_a_too_short_payload_returns_invalid_response() {
+        let request = protocol::query().unwrap();
+        // too short to hold RSSI + PC word + an EPC + tag CRC, but long enough to miss the
+        // "no tag" shortcut -- a corrupted frame should error, not panic on the EPC slice
+        let response = response_frame(Command::Query, &[0xC0, 0x00, 0x00]);
+
+        let mut device = M100Device::with_transport(MockTransport::new(vec![(request, response)]));
+
+        let err = device.query().unwrap_err();
+        assert!(err.to_string().contains("did not match the expected shape"));
+        device.assert_transport_done();
+    }
+
+    #[test]
+    fn query_with_no_tag_returns_none() {
+        let request = protocol::query().unwrap();
+        let response = response_frame(Command::Query, &[0x00]);
+
+        let mut device = M100Device::with_transport(MockTransport::new(vec![(request, response)]));
+
+        assert!(device.query().unwrap().is_none());
+        device.assert_transport_done();
+    }
+
+    #[test]
+    fn read_chunked_data_stops_at_the_first_error() {
+        let password = DEFAULT_PASSWORD;
+        // EPC reads start by reading the header words (address 0, length 12) before
+        // chunking the tag data itself starting at address 12.
+        let header = protocol::read_data(&password, MemoryBank::Epc, 0, 12).unwrap();
+        let first = protocol::read_data(&password, MemoryBank::Epc, 12, 2).unwrap();
+        let second = protocol::read_data(&password, MemoryBank::Epc, 14, 2).unwrap();
+
+        let mut device = M100Device::with_transport(MockTransport::new(vec![
+            (header, response_frame(Command::ReadData, &[0; 12])),
+            (first, response_frame(Command::ReadData, &[0x11, 0x22])),
+            (second, response_frame(Command::ReadData, &[0xA3])),
+        ]));
+
+        let data = device.read_all_data(&password, MemoryBank::Epc).unwrap();
+        assert_eq!(data, [vec![0; 12], vec![0x11, 0x22]].concat());
+        device.assert_transport_done();
+    }
+
+    #[test]
+    fn write_epc_reports_the_firmware_write_error() {
+        let password = DEFAULT_PASSWORD;
+        let epc = [0xAB, 0xCD];
+        let request = protocol::write_epc(&password, &epc).unwrap();
+
+        let mut device = M100Device::with_transport(MockTransport::new(vec![(
+            request,
+            response_frame(Command::WriteData, &[0xB0]),
+        )]));
+
+        let err = device
+            .write_data(&password, MemoryBank::Epc, 0, &mut epc.clone())
+            .unwrap_err();
+        assert!(err.to_string().contains("write failure"));
+        device.assert_transport_done();
+    }
+
+    #[test]
+    fn inventory_resyncs_past_a_stray_byte_between_reports() {
+        let command = protocol::multiple_polling(INVENTORY_POLLING_CYCLES).unwrap();
+        let tag_a = response_frame(Command::Query, &[0xC0, 0x00, 0x00, 0xAA, 0xAA, 0x12, 0x34]);
+        let tag_b = response_frame(Command::Query, &[0xC0, 0x00, 0x00, 0xBB, 0xBB, 0x12, 0x34]);
+
+        // a stray byte between the two reports (e.g. line noise, or the tail end of a report
+        // cut short by a read timeout) must not permanently desync the parser -- it should be
+        // dropped and the next report still parsed correctly.
+        let mut response = tag_a;
+        response.push(0x00);
+        response.extend_from_slice(&tag_b);
+
+        let idle_request = protocol::idle().unwrap();
+        let idle_response = response_frame(Command::Idle, &[]);
+
+        let mut device = M100Device::with_transport(MockTransport::new(vec![
+            (command, response),
+            (idle_request, idle_response),
+        ]));
+
+        let tags = device.inventory(Duration::from_millis(50), |_| {}).unwrap();
+        let mut epcs: Vec<_> = tags.iter().map(|t| t.epc.clone()).collect();
+        epcs.sort();
+        assert_eq!(epcs, vec!["AAAA".to_string(), "BBBB".to_string()]);
+        device.assert_transport_done();
+    }
+}
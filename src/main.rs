@@ -1,18 +1,29 @@
 use std::time::Duration;
 
-use crate::m100::{M100Device, MemoryBank};
+use anyhow::{anyhow, Result};
 use clap::{Parser, ValueEnum};
-use serialport::ClearBuffer;
+use serialport::{ClearBuffer, DataBits, StopBits};
+
+use crate::m100::{M100Device, MemoryBank};
 
+pub mod codec;
+pub mod dispatch;
+pub mod error;
+pub mod inventory;
 pub mod m100;
 pub mod protocol;
+pub mod transport;
 
 #[derive(clap::Parser)]
 struct Cli {
     #[command(subcommand)]
     action: Action,
-    #[arg(short, long, default_value = "/dev/ttyACM0")]
-    port: String,
+    #[arg(
+        short,
+        long,
+        help = "Serial port the M100 is connected to; auto-detected if omitted"
+    )]
+    port: Option<String>,
 }
 
 #[derive(clap::Subcommand, PartialEq)]
@@ -27,6 +38,16 @@ enum Action {
     },
     #[command(about = "Read information about the EPC Gen2 tag")]
     Identify,
+    #[command(about = "Scan for every tag in range and print a live inventory table")]
+    Inventory {
+        #[arg(
+            short,
+            long,
+            default_value_t = 10,
+            help = "How long to scan for, in seconds"
+        )]
+        seconds: u64,
+    },
 }
 
 #[derive(ValueEnum, Clone, PartialEq)]
@@ -39,10 +60,27 @@ enum CliMemoryBank {
 fn main() {
     let args = Cli::parse();
 
-    let mut port = match serialport::new(&args.port, 115200).open() {
+    let port_name = match args.port {
+        Some(port) => port,
+        None => {
+            println!("No port given, auto-detecting the M100...");
+            match detect_port() {
+                Ok(port) => {
+                    println!("Found an M100 on `{}`.", port);
+                    port
+                }
+                Err(e) => {
+                    println!("Failed to auto-detect a serial port: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+    };
+
+    let mut port = match serialport::new(&port_name, 115200).open() {
         Ok(port) => port,
         Err(_) => {
-            println!("Failed to open serial port `{}`.", args.port);
+            println!("Failed to open serial port `{}`.", port_name);
             std::process::exit(1);
         }
     };
@@ -60,7 +98,10 @@ fn main() {
     let version = match m100.get_version() {
         Ok(version) => version,
         Err(e) => {
-            println!("Failed to identify device. Are you sure it's working? {}", e);
+            println!(
+                "Failed to identify device. Are you sure it's working? {}",
+                e
+            );
             std::process::exit(1);
         }
     };
@@ -70,81 +111,148 @@ fn main() {
     }
     println!("Connected to '{}'.", version);
 
-    println!("Waiting for a tag...");
     match args.action {
         Action::Identify => unreachable!(),
-        Action::Read { bank } => loop {
-            if let Ok(Some(qr)) = m100.query() {
-                println!("Tag found! EPC: {}", qr.epc);
-                if bank == CliMemoryBank::Epc {
-                    break;
-                }
-                let data = match m100.read_all_data(
-                    &m100::DEFAULT_PASSWORD,
-                    match bank {
-                        CliMemoryBank::Epc => MemoryBank::Epc,
-                        CliMemoryBank::Tid => MemoryBank::Tid,
-                        CliMemoryBank::User => MemoryBank::User,
-                    },
-                ) {
-                    Ok(data) => data,
-                    Err(e) => {
-                        eprintln!("Error occurred: {e}.\nTrying again...");
-                        continue;
+        Action::Read { bank } => {
+            println!("Waiting for a tag...");
+            loop {
+                if let Ok(Some(qr)) = m100.query() {
+                    println!("Tag found! EPC: {}", qr.epc);
+                    if bank == CliMemoryBank::Epc {
+                        break;
                     }
-                };
+                    let data = match m100.read_all_data(
+                        &m100::DEFAULT_PASSWORD,
+                        match bank {
+                            CliMemoryBank::Epc => MemoryBank::Epc,
+                            CliMemoryBank::Tid => MemoryBank::Tid,
+                            CliMemoryBank::User => MemoryBank::User,
+                        },
+                    ) {
+                        Ok(data) => data,
+                        Err(e) => {
+                            eprintln!("Error occurred: {e}.\nTrying again...");
+                            continue;
+                        }
+                    };
 
-                println!(
-                    "\nData received from tag: {}",
-                    hex::encode(data).to_uppercase()
-                );
+                    println!(
+                        "\nData received from tag: {}",
+                        hex::encode(data).to_uppercase()
+                    );
 
-                break;
-            }
-        },
-        Action::Write { bank, value } => loop {
-            let bank = match bank {
-                CliMemoryBank::Epc => MemoryBank::Epc,
-                CliMemoryBank::Tid => MemoryBank::Tid,
-                CliMemoryBank::User => MemoryBank::User,
-            };
-            if let Ok(Some(qr)) = m100.query() {
-                println!("Tag found! EPC: {}", qr.epc);
-
-                let mut write_data = hex::decode(&value).unwrap();
-                match m100.write_data(&m100::DEFAULT_PASSWORD, bank, 0, &mut write_data) {
-                    Ok(_) => (),
-                    Err(e) => {
-                        eprintln!("Error occurred during writing: {e}. Retrying...");
-                        continue;
-                    }
+                    break;
                 }
+            }
+        }
+        Action::Write { bank, value } => {
+            println!("Waiting for a tag...");
+            loop {
+                let bank = match bank {
+                    CliMemoryBank::Epc => MemoryBank::Epc,
+                    CliMemoryBank::Tid => MemoryBank::Tid,
+                    CliMemoryBank::User => MemoryBank::User,
+                };
+                if let Ok(Some(qr)) = m100.query() {
+                    println!("Tag found! EPC: {}", qr.epc);
 
-                println!("Verifying data, please keep the tag on the reader...");
-                let verify_data = if bank == MemoryBank::Epc {
-                    loop {
-                        if let Ok(Some(qr)) = m100.query() {
-                            break hex::decode(qr.epc).unwrap();
-                        }
-                    }
-                } else {
-                    match m100.read_all_data(&m100::DEFAULT_PASSWORD, bank) {
-                        Ok(data) => data,
+                    let mut write_data = hex::decode(&value).unwrap();
+                    match m100.write_data(&m100::DEFAULT_PASSWORD, bank, 0, &mut write_data) {
+                        Ok(_) => (),
                         Err(e) => {
-                            eprintln!("Error occurred during verification: {e}.\nTrying again...");
+                            eprintln!("Error occurred during writing: {e}. Retrying...");
                             continue;
                         }
                     }
-                };
 
-                if write_data != verify_data {
-                    eprintln!("Verification failed. Trying again...");
-                    continue;
-                }
+                    println!("Verifying data, please keep the tag on the reader...");
+                    let verify_data = if bank == MemoryBank::Epc {
+                        loop {
+                            if let Ok(Some(qr)) = m100.query() {
+                                break hex::decode(qr.epc).unwrap();
+                            }
+                        }
+                    } else {
+                        match m100.read_all_data(&m100::DEFAULT_PASSWORD, bank) {
+                            Ok(data) => data,
+                            Err(e) => {
+                                eprintln!(
+                                    "Error occurred during verification: {e}.\nTrying again..."
+                                );
+                                continue;
+                            }
+                        }
+                    };
 
-                println!("\nSuccessfully wrote data!");
-                break;
+                    if write_data != verify_data {
+                        eprintln!("Verification failed. Trying again...");
+                        continue;
+                    }
+
+                    println!("\nSuccessfully wrote data!");
+                    break;
+                }
             }
-        },
+        }
+        Action::Inventory { seconds } => {
+            let tags = match m100.inventory(Duration::from_secs(seconds), print_inventory_table) {
+                Ok(tags) => tags,
+                Err(e) => {
+                    println!("Error occurred during inventory scan: {e}.");
+                    std::process::exit(1);
+                }
+            };
+            println!("\nInventory complete: {} unique tag(s) found.", tags.len());
+        }
+    }
+}
+
+/// Enumerates the system's serial ports and opens each candidate at 115200 baud, probing it
+/// with a `get_version` request. Returns the name of the first port that comes back with a
+/// well-formed reply (correct `0xBB`/`0x7E` framing and checksum), matching how flashing
+/// tools locate their device when no port is given.
+fn detect_port() -> Result<String> {
+    for port_info in serialport::available_ports()? {
+        let mut port = match serialport::new(&port_info.port_name, 115200)
+            .timeout(Duration::from_millis(500))
+            .open()
+        {
+            Ok(port) => port,
+            Err(_) => continue,
+        };
+
+        // Skip M100Device::new here -- it'd reset this port's 500ms probe timeout to the
+        // full 1s used for normal operation, doubling how long an unresponsive port takes
+        // to rule out.
+        if port.set_stop_bits(StopBits::One).is_err()
+            || port.set_data_bits(DataBits::Eight).is_err()
+        {
+            continue;
+        }
+        let mut device = M100Device::with_transport(port);
+
+        if device.get_version().is_ok() {
+            return Ok(port_info.port_name);
+        }
+    }
+
+    Err(anyhow!(
+        "could not find an M100 on any available serial port"
+    ))
+}
+
+fn print_inventory_table(tags: &std::collections::HashMap<String, inventory::InventoryEntry>) {
+    print!("\x1B[2J\x1B[H"); // clear the screen and move the cursor home
+    println!(
+        "{:<26} {:>6} {:>5} {:>5} {:>5}",
+        "EPC", "READS", "MIN", "MAX", "LAST"
+    );
+    let mut rows: Vec<_> = tags.values().collect();
+    rows.sort_by(|a, b| a.epc.cmp(&b.epc));
+    for tag in rows {
+        println!(
+            "{:<26} {:>6} {:>5} {:>5} {:>5}",
+            tag.epc, tag.read_count, tag.min_rssi, tag.max_rssi, tag.last_rssi
+        );
     }
 }
@@ -10,11 +10,37 @@ pub enum Command {
     GetVersion = 0x03,
     Idle = 0x04,
     Query = 0x22,
+    MultiplePolling = 0x27,
     ReadData = 0x39,
     SetHfss = 0xAD,
     WriteData = 0x49,
 }
 
+impl TryFrom<u8> for Command {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Command> {
+        Ok(match value {
+            0x03 => Command::GetVersion,
+            0x04 => Command::Idle,
+            0x22 => Command::Query,
+            0x27 => Command::MultiplePolling,
+            0x39 => Command::ReadData,
+            0xAD => Command::SetHfss,
+            0x49 => Command::WriteData,
+            other => return Err(anyhow::anyhow!("unknown command byte: {:#04x}", other)),
+        })
+    }
+}
+
+/// A fully decoded M100 frame: `0xBB`, `frame_type`, `command`, length-prefixed `payload`, checksum, `0x7E`.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub frame_type: u8,
+    pub command: Command,
+    pub payload: Vec<u8>,
+}
+
 pub fn get_version() -> Result<Vec<u8>> {
     make_frame(Command::GetVersion, &[0x00])
 }
@@ -31,7 +57,23 @@ pub fn idle() -> Result<Vec<u8>> {
     make_frame(Command::Idle, &[0x00, 0x01, 0x00])
 }
 
-pub fn read_data(password: &[u8], bank: MemoryBank, address: u16, data_length: u16) -> Result<Vec<u8>> {
+/// Issues the reader's multiple-polling instruction, asking it to keep reporting every tag
+/// it sees (one `Query`-shaped notification frame per read) for `cycles` polling rounds
+/// instead of just the one report `query()` gets.
+pub fn multiple_polling(cycles: u16) -> Result<Vec<u8>> {
+    let mut payload = Vec::new();
+    payload.write_u8(0x22)?; // reserved
+    payload.write_u16::<BE>(cycles)?;
+
+    make_frame(Command::MultiplePolling, &payload)
+}
+
+pub fn read_data(
+    password: &[u8],
+    bank: MemoryBank,
+    address: u16,
+    data_length: u16,
+) -> Result<Vec<u8>> {
     let mut payload = Vec::new();
     payload.write(password)?;
     payload.write_u8(bank as u8)?;
@@ -45,7 +87,7 @@ pub fn write_data(password: &[u8], bank: MemoryBank, address: u16, data: &[u8])
     if bank == MemoryBank::Epc {
         panic!("use write_epc instead");
     }
-    
+
     let mut payload = Vec::new();
     payload.write(password)?;
     payload.write_u8(bank as u8)?;
@@ -86,7 +128,5 @@ pub fn make_frame(cmd: Command, payload: &[u8]) -> Result<Vec<u8>> {
     packet.write_u8((checksum & 0xFF) as u8)?; // checksum
     packet.write_u8(0x7E)?; // MAGICRF_TAIL
 
-    println!("cmd {:?} made frame {:2x?}", cmd, packet);
-
     Ok(packet)
 }
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use serialport::SerialPort;
+use std::io::{Read, Write};
+
+/// Abstracts the serial link an [`M100Device`](crate::m100::M100Device) talks over, so the
+/// protocol logic (frame building, chunked reads, EPC PC-word computation, status-code
+/// handling) can be exercised against a scripted [`MockTransport`] instead of real hardware.
+pub trait M100Transport {
+    fn write_all(&mut self, buf: &[u8]) -> Result<()>;
+    fn flush(&mut self) -> Result<()>;
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()>;
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()>;
+}
+
+impl M100Transport for Box<dyn SerialPort> {
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        Ok(Write::write_all(self, buf)?)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(Write::flush(self)?)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        Ok(Read::read_exact(self, buf)?)
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        Ok(SerialPort::set_baud_rate(self.as_mut(), baud_rate)?)
+    }
+}
+
+/// A scripted [`M100Transport`] for unit tests. Constructed from an ordered list of
+/// `(expected_request_bytes, canned_response_bytes)` pairs, it asserts that every
+/// `write_all` call matches the next expected request and then feeds the corresponding
+/// canned response back through `read_exact`.
+#[cfg(test)]
+pub struct MockTransport {
+    steps: std::collections::VecDeque<(Vec<u8>, Vec<u8>)>,
+    pending_response: std::collections::VecDeque<u8>,
+}
+
+#[cfg(test)]
+impl MockTransport {
+    pub fn new(steps: Vec<(Vec<u8>, Vec<u8>)>) -> MockTransport {
+        MockTransport {
+            steps: steps.into(),
+            pending_response: std::collections::VecDeque::new(),
+        }
+    }
+
+    /// Panics unless every scripted step was consumed.
+    pub fn assert_done(&self) {
+        assert!(
+            self.steps.is_empty() && self.pending_response.is_empty(),
+            "MockTransport has {} unconsumed step(s) left",
+            self.steps.len()
+        );
+    }
+}
+
+#[cfg(test)]
+impl M100Transport for MockTransport {
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        let (expected, response) = self
+            .steps
+            .pop_front()
+            .unwrap_or_else(|| panic!("unexpected write with no scripted step left: {:02x?}", buf));
+        assert_eq!(expected, buf, "write did not match the scripted request");
+        self.pending_response.extend(response);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        for byte in buf.iter_mut() {
+            *byte = self
+                .pending_response
+                .pop_front()
+                .ok_or_else(|| anyhow::anyhow!("read past the end of the scripted response"))?;
+        }
+        Ok(())
+    }
+
+    fn set_baud_rate(&mut self, _baud_rate: u32) -> Result<()> {
+        Ok(())
+    }
+}